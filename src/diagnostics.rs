@@ -0,0 +1,72 @@
+//! Types for deserializing diagnostics from `rustc`'s `--error-format=json` output.
+//!
+//! These mirror the (unstable) JSON diagnostic format rustc emits; fields
+//! that are not listed here are ignored when deserializing.
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub code: Option<DiagnosticCode>,
+    pub level: String,
+    pub spans: Vec<DiagnosticSpan>,
+    pub children: Vec<Diagnostic>,
+    pub rendered: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DiagnosticCode {
+    /// The code itself, e.g. `E0308`.
+    pub code: String,
+    /// An explanation for the code.
+    pub explanation: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DiagnosticSpan {
+    pub file_name: String,
+    pub byte_start: u32,
+    pub byte_end: u32,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    pub is_primary: bool,
+    pub text: Vec<DiagnosticSpanLine>,
+    /// Label that should be placed at this location (if any)
+    pub label: Option<String>,
+    /// If we are suggesting a replacement, this will contain text
+    /// that should be sliced in atop this span.
+    pub suggested_replacement: Option<String>,
+    /// If the suggestion is approximate
+    pub suggestion_applicability: Option<Applicability>,
+    /// Macro invocations that created the code at this span, if any.
+    pub expansion: Option<Box<DiagnosticSpanMacroExpansion>>,
+}
+
+/// How likely a suggestion is to be automatically applicable without a
+/// human reviewing it, as reported by rustc alongside a suggested span.
+#[derive(Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Applicability {
+    MachineApplicable,
+    HasPlaceholders,
+    MaybeIncorrect,
+    Unspecified,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DiagnosticSpanLine {
+    pub text: String,
+    /// 1-based, character offset in self.text
+    pub highlight_start: usize,
+    pub highlight_end: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DiagnosticSpanMacroExpansion {
+    /// span where macro was applied to generate this code
+    pub span: DiagnosticSpan,
+    /// name of macro that was applied (e.g., "foo!" or "#[derive(Eq)]")
+    pub macro_decl_name: String,
+    /// span where macro was defined (if known)
+    pub def_site_span: Option<DiagnosticSpan>,
+}
@@ -2,8 +2,21 @@
 extern crate serde_derive;
 extern crate serde_json;
 
+use std::collections::HashSet;
+use std::ops::Range;
+
 pub mod diagnostics;
-use diagnostics::{Diagnostic, DiagnosticSpan};
+pub mod replace;
+use diagnostics::{Applicability, Diagnostic, DiagnosticSpan};
+
+/// Controls which suggestions `collect_suggestions` returns.
+#[derive(Debug, Copy, Clone, Hash, PartialEq)]
+pub enum Filter {
+    /// Only return suggestions that are safe to apply without review.
+    MachineApplicableOnly,
+    /// Return every suggestion, regardless of applicability.
+    Everything,
+}
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq)]
 pub struct LinePosition {
@@ -33,6 +46,19 @@ impl std::fmt::Display for LineRange {
 pub struct Suggestion {
     pub message: String,
     pub snippets: Vec<Snippet>,
+    pub solutions: Vec<Solution>,
+}
+
+/// One self-consistent way of fixing a `Suggestion`.
+///
+/// rustc calls this a "substitution": a suggestion can offer several
+/// candidate solutions, and a solution can touch several non-contiguous
+/// spans that only make sense together (e.g. inserting an opening token in
+/// one place and a closing token in another). An applier must apply every
+/// `Replacement` in a solution, or none of them.
+#[derive(Debug, Clone, Hash, PartialEq)]
+pub struct Solution {
+    pub message: String,
     pub replacements: Vec<Replacement>,
 }
 
@@ -41,6 +67,8 @@ pub struct Snippet {
     pub sub_message: Option<String>,
     pub file_name: String,
     pub line_range: LineRange,
+    /// byte range of the text to replace, relative to the start of the file
+    pub range: Range<usize>,
     /// leading surrounding text, text to replace, trailing surrounding text
     ///
     /// This split is useful for higlighting the part that gets replaced
@@ -51,6 +79,7 @@ pub struct Snippet {
 pub struct Replacement {
     pub snippet: Snippet,
     pub replacement: String,
+    pub applicability: Applicability,
 }
 
 fn parse_snippet(message: Option<String>, span: &DiagnosticSpan) -> Snippet {
@@ -90,6 +119,7 @@ fn parse_snippet(message: Option<String>, span: &DiagnosticSpan) -> Snippet {
                 column: span.column_end,
             },
         },
+        range: (span.byte_start as usize)..(span.byte_end as usize),
         text: (lead, body, tail),
     }
 }
@@ -98,32 +128,243 @@ fn collect_span(message: Option<String>, span: &DiagnosticSpan) -> Option<Replac
     span.suggested_replacement.clone().map(|replacement| Replacement {
         snippet: parse_snippet(message, span),
         replacement,
+        applicability: span.suggestion_applicability.unwrap_or(Applicability::Unspecified),
     })
 }
 
-pub fn collect_suggestions(diagnostic: &Diagnostic) -> Option<Suggestion> {
-    let mut replacements = vec![];
+/// Every span of a child diagnostic belongs together: they are the one
+/// solution that child is suggesting. A `Solution` must be applied in full
+/// or not at all, so `filter` is decided once for the whole solution rather
+/// than per-`Replacement` -- otherwise a filtered-out span could be dropped
+/// while its sibling spans are kept, applying only part of the edit.
+fn collect_solution(child: &Diagnostic, filter: Filter) -> Option<Solution> {
+    let replacements: Vec<Replacement> = child.spans
+        .iter()
+        .filter_map(|span| collect_span(Some(child.message.clone()), span))
+        .collect();
+
+    if replacements.is_empty() {
+        return None;
+    }
+
+    if filter == Filter::MachineApplicableOnly
+        && !replacements
+            .iter()
+            .all(|replacement| replacement.applicability == Applicability::MachineApplicable)
+    {
+        return None;
+    }
 
+    Some(Solution {
+        message: child.message.clone(),
+        replacements,
+    })
+}
+
+pub fn collect_suggestions(diagnostic: &Diagnostic, filter: Filter) -> Option<Suggestion> {
     let snippets = diagnostic.spans
         .iter()
         .map(|span| parse_snippet(None, span))
         .collect();
 
-    for child in &diagnostic.children {
-        for span in &child.spans {
-            if let Some(sugg) = collect_span(Some(child.message.clone()), span) {
-                replacements.push(sugg);
-            }
-        }
-    }
+    let solutions: Vec<Solution> = diagnostic.children
+        .iter()
+        .filter_map(|child| collect_solution(child, filter))
+        .collect();
 
-    if replacements.is_empty() {
+    if solutions.is_empty() {
         None
     } else {
         Some(Suggestion {
             message: diagnostic.message.clone(),
             snippets,
-            replacements,
+            solutions,
         })
     }
 }
+
+/// Parses `rustc`'s newline-delimited `--error-format=json` output and
+/// collects every suggestion it contains.
+///
+/// If `only` is non-empty, diagnostics whose lint code is not in it are
+/// skipped (as well as diagnostics that have no code at all).
+pub fn get_suggestions_from_json(
+    input: &str,
+    only: &HashSet<String>,
+    filter: Filter,
+) -> serde_json::Result<Vec<Suggestion>> {
+    let mut suggestions = vec![];
+    for diagnostic in serde_json::Deserializer::from_str(input).into_iter::<Diagnostic>() {
+        let diagnostic = diagnostic?;
+
+        if !only.is_empty() {
+            // `Option::is_some_and` isn't available on this crate's MSRV.
+            #[allow(clippy::unnecessary_map_or)]
+            let matches_code = diagnostic.code
+                .as_ref()
+                .map_or(false, |code| only.contains(&code.code));
+            if !matches_code {
+                continue;
+            }
+        }
+
+        if let Some(suggestion) = collect_suggestions(&diagnostic, filter) {
+            suggestions.push(suggestion);
+        }
+    }
+    Ok(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diagnostics::DiagnosticSpanLine;
+
+    fn span(highlight_start: usize, highlight_end: usize, byte_start: u32, byte_end: u32) -> DiagnosticSpan {
+        span_with_applicability(
+            highlight_start,
+            highlight_end,
+            byte_start,
+            byte_end,
+            Applicability::MachineApplicable,
+        )
+    }
+
+    fn span_with_applicability(
+        highlight_start: usize,
+        highlight_end: usize,
+        byte_start: u32,
+        byte_end: u32,
+        applicability: Applicability,
+    ) -> DiagnosticSpan {
+        DiagnosticSpan {
+            file_name: "foo.rs".to_string(),
+            byte_start,
+            byte_end,
+            line_start: 1,
+            line_end: 1,
+            column_start: highlight_start,
+            column_end: highlight_end,
+            is_primary: true,
+            text: vec![DiagnosticSpanLine {
+                text: "let mut x = 1;".to_string(),
+                highlight_start,
+                highlight_end,
+            }],
+            label: None,
+            suggested_replacement: Some("mut ".to_string()),
+            suggestion_applicability: Some(applicability),
+            expansion: None,
+        }
+    }
+
+    fn child(message: &str, spans: Vec<DiagnosticSpan>) -> Diagnostic {
+        Diagnostic {
+            message: message.to_string(),
+            code: None,
+            level: "help".to_string(),
+            spans,
+            children: vec![],
+            rendered: None,
+        }
+    }
+
+    #[test]
+    fn spans_from_the_same_child_form_one_solution_separate_children_form_separate_solutions() {
+        let diagnostic = Diagnostic {
+            message: "cannot borrow as mutable".to_string(),
+            code: None,
+            level: "error".to_string(),
+            spans: vec![],
+            children: vec![
+                child("consider changing this to be mutable", vec![
+                    span(5, 5, 4, 4),
+                    span(15, 15, 14, 14),
+                ]),
+                child("consider cloning instead", vec![span(1, 15, 0, 14)]),
+            ],
+            rendered: None,
+        };
+
+        let suggestion = collect_suggestions(&diagnostic, Filter::Everything).unwrap();
+        assert_eq!(suggestion.solutions.len(), 2);
+        assert_eq!(suggestion.solutions[0].replacements.len(), 2);
+        assert_eq!(suggestion.solutions[1].replacements.len(), 1);
+    }
+
+    #[test]
+    fn machine_applicable_only_drops_non_machine_applicable_solutions() {
+        let diagnostic = Diagnostic {
+            message: "cannot borrow as mutable".to_string(),
+            code: None,
+            level: "error".to_string(),
+            spans: vec![],
+            children: vec![child(
+                "maybe you meant this",
+                vec![span_with_applicability(5, 5, 4, 4, Applicability::MaybeIncorrect)],
+            )],
+            rendered: None,
+        };
+
+        assert!(collect_suggestions(&diagnostic, Filter::MachineApplicableOnly).is_none());
+        assert!(collect_suggestions(&diagnostic, Filter::Everything).is_some());
+    }
+
+    #[test]
+    fn machine_applicable_only_drops_the_whole_solution_on_mixed_applicability() {
+        // A single solution ("insert `{` here, `}` there") where one span is
+        // machine-applicable and the other isn't must be dropped entirely:
+        // keeping only the applicable half would apply a corrupt partial edit.
+        let diagnostic = Diagnostic {
+            message: "mismatched delimiters".to_string(),
+            code: None,
+            level: "error".to_string(),
+            spans: vec![],
+            children: vec![child(
+                "insert the missing delimiters",
+                vec![
+                    span_with_applicability(5, 5, 4, 4, Applicability::MachineApplicable),
+                    span_with_applicability(15, 15, 14, 14, Applicability::MaybeIncorrect),
+                ],
+            )],
+            rendered: None,
+        };
+
+        assert!(collect_suggestions(&diagnostic, Filter::MachineApplicableOnly).is_none());
+
+        let suggestion = collect_suggestions(&diagnostic, Filter::Everything).unwrap();
+        assert_eq!(suggestion.solutions[0].replacements.len(), 2);
+    }
+
+    // Three newline-delimited diagnostics: one with lint code "E1", one with
+    // "E2", and one with no `code` at all.
+    const JSON_DIAGNOSTICS: &str = r#"
+{"message":"unused variable: `x`","code":{"code":"E1","explanation":null},"level":"warning","spans":[],"children":[{"message":"consider prefixing with underscore","code":null,"level":"help","spans":[{"file_name":"foo.rs","byte_start":4,"byte_end":4,"line_start":1,"line_end":1,"column_start":5,"column_end":5,"is_primary":true,"text":[{"text":"let mut x = 1;","highlight_start":5,"highlight_end":5}],"label":null,"suggested_replacement":"_","suggestion_applicability":"MachineApplicable","expansion":null}],"children":[],"rendered":null}],"rendered":null}
+{"message":"unused import","code":{"code":"E2","explanation":null},"level":"warning","spans":[],"children":[{"message":"remove the import","code":null,"level":"help","spans":[{"file_name":"foo.rs","byte_start":0,"byte_end":13,"line_start":1,"line_end":1,"column_start":1,"column_end":14,"is_primary":true,"text":[{"text":"use foo::bar;","highlight_start":1,"highlight_end":14}],"label":null,"suggested_replacement":"","suggestion_applicability":"MachineApplicable","expansion":null}],"children":[],"rendered":null}],"rendered":null}
+{"message":"no code diagnostic","code":null,"level":"warning","spans":[],"children":[{"message":"do something","code":null,"level":"help","spans":[{"file_name":"foo.rs","byte_start":0,"byte_end":1,"line_start":1,"line_end":1,"column_start":1,"column_end":2,"is_primary":true,"text":[{"text":"x","highlight_start":1,"highlight_end":2}],"label":null,"suggested_replacement":"y","suggestion_applicability":"MachineApplicable","expansion":null}],"children":[],"rendered":null}],"rendered":null}
+"#;
+
+    #[test]
+    fn get_suggestions_from_json_filters_by_lint_code() {
+        let mut only = HashSet::new();
+        only.insert("E1".to_string());
+
+        let suggestions =
+            get_suggestions_from_json(JSON_DIAGNOSTICS, &only, Filter::Everything).unwrap();
+
+        // "E2" is filtered out by `only`, and the code-less diagnostic is
+        // skipped too since `only` is non-empty.
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].message, "unused variable: `x`");
+    }
+
+    #[test]
+    fn get_suggestions_from_json_with_empty_only_keeps_every_diagnostic() {
+        let only = HashSet::new();
+
+        let suggestions =
+            get_suggestions_from_json(JSON_DIAGNOSTICS, &only, Filter::Everything).unwrap();
+
+        assert_eq!(suggestions.len(), 3);
+    }
+}
@@ -0,0 +1,241 @@
+//! Applies `Replacement`s produced by [`collect_suggestions`] back onto the
+//! original source, the missing other half of "apply the suggestions made by
+//! rustc".
+//!
+//! [`collect_suggestions`]: ../fn.collect_suggestions.html
+
+use std::fmt;
+use std::ops::Range;
+use std::str;
+
+use Replacement;
+use Solution;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The file did not contain valid UTF-8 after applying replacements.
+    Utf8Error(str::Utf8Error),
+    /// The replacement could not be applied because it overlaps with a part
+    /// of the file that was already replaced by an earlier edit.
+    ReplacementConflict {
+        first: Range<usize>,
+        second: Range<usize>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Utf8Error(ref err) => write!(f, "file did not contain valid utf8: {}", err),
+            Error::ReplacementConflict {
+                ref first,
+                ref second,
+            } => write!(
+                f,
+                "replacement at {:?} conflicts with an already applied replacement at {:?}",
+                second, first
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Utf8Error(..) => "invalid utf8",
+            Error::ReplacementConflict { .. } => "conflicting replacements",
+        }
+    }
+}
+
+/// One piece of the file as it is rewritten: either a still-untouched slice
+/// of the original bytes, or bytes that were substituted in by an applied
+/// `Replacement`.
+#[derive(Debug, Clone)]
+enum Part {
+    Original(Range<usize>),
+    Replaced {
+        data: Box<[u8]>,
+        /// number of original bytes (relative to the start of this part)
+        /// that this replacement consumed
+        inserted: usize,
+    },
+}
+
+fn part_end(part: &Part, original_start: usize) -> usize {
+    match *part {
+        Part::Original(ref range) => range.end,
+        Part::Replaced { inserted, .. } => original_start + inserted,
+    }
+}
+
+/// Applies a set of `Replacement`s to the original contents of a file.
+///
+/// Replacements are applied one at a time; if a replacement would overlap
+/// with one already applied, the whole operation is rejected instead of
+/// silently corrupting the output (see [`apply`]).
+///
+/// [`apply`]: #method.apply
+pub struct CodeFix {
+    original: Vec<u8>,
+    parts: Vec<Part>,
+}
+
+impl CodeFix {
+    pub fn new(file: &str) -> CodeFix {
+        let original = file.as_bytes().to_vec();
+        let len = original.len();
+        CodeFix {
+            original,
+            parts: vec![Part::Original(0..len)],
+        }
+    }
+
+    /// Applies every replacement of `solution` as a single, transactional
+    /// edit: either all of them are applied, or (on the first conflict) none
+    /// of them are and `self` is left untouched.
+    pub fn apply(&mut self, solution: &Solution) -> Result<(), Error> {
+        let mut parts = self.parts.clone();
+        for replacement in &solution.replacements {
+            apply_replacement(&mut parts, replacement)?;
+        }
+        self.parts = parts;
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<String, Error> {
+        let mut bytes = Vec::with_capacity(self.original.len());
+        for part in &self.parts {
+            match *part {
+                Part::Original(ref range) => bytes.extend_from_slice(&self.original[range.clone()]),
+                Part::Replaced { ref data, .. } => bytes.extend_from_slice(data),
+            }
+        }
+        String::from_utf8(bytes).map_err(|err| Error::Utf8Error(err.utf8_error()))
+    }
+}
+
+fn apply_replacement(parts: &mut Vec<Part>, replacement: &Replacement) -> Result<(), Error> {
+    let range = replacement.snippet.range.clone();
+
+    // Find the part whose original range contains `range.start`. A
+    // zero-width replacement (e.g. rustc's common "insert `;`" suggestions)
+    // produces a `Replaced` part and the following `Original` part that
+    // share the same original-offset start, so `starts` can contain
+    // duplicates. `binary_search` makes no promise about which duplicate it
+    // returns, so we use `partition_point` instead: it is specified to
+    // return the first index for which the predicate is false, which for a
+    // `<=` predicate always lands on the *last* matching duplicate, i.e. the
+    // part that comes after the zero-width insertion rather than the
+    // insertion itself.
+    let mut original_start = 0;
+    let mut starts = Vec::with_capacity(parts.len());
+    for part in parts.iter() {
+        starts.push(original_start);
+        original_start = part_end(part, original_start);
+    }
+
+    let idx = starts.partition_point(|&start| start <= range.start).saturating_sub(1);
+
+    let part_original_start = starts[idx];
+    let part = parts[idx].clone();
+
+    match part {
+        Part::Replaced { .. } => Err(Error::ReplacementConflict {
+            first: part_original_start..part_end(&part, part_original_start),
+            second: range,
+        }),
+        Part::Original(orig_range) => {
+            if range.start < orig_range.start || range.end > orig_range.end {
+                // `range` runs on past this untouched part: into the part
+                // after it, which is always an already applied replacement,
+                // or, where there is none, past the end of the file.
+                let first = match parts.get(idx + 1) {
+                    Some(next) => starts[idx + 1]..part_end(next, starts[idx + 1]),
+                    None => orig_range,
+                };
+                return Err(Error::ReplacementConflict {
+                    first,
+                    second: range,
+                });
+            }
+
+            let mut replacement_parts = Vec::with_capacity(3);
+            if orig_range.start < range.start {
+                replacement_parts.push(Part::Original(orig_range.start..range.start));
+            }
+            replacement_parts.push(Part::Replaced {
+                data: replacement.replacement.as_bytes().to_vec().into_boxed_slice(),
+                inserted: range.end - range.start,
+            });
+            if range.end < orig_range.end {
+                replacement_parts.push(Part::Original(range.end..orig_range.end));
+            }
+
+            parts.splice(idx..idx + 1, replacement_parts);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Applicability, LinePosition, LineRange, Replacement, Snippet, Solution};
+
+    fn replacement(range: Range<usize>, text: &str) -> Replacement {
+        Replacement {
+            snippet: Snippet {
+                sub_message: None,
+                file_name: "foo.rs".to_string(),
+                line_range: LineRange {
+                    start: LinePosition { line: 1, column: 1 },
+                    end: LinePosition { line: 1, column: 1 },
+                },
+                range,
+                text: (String::new(), String::new(), String::new()),
+            },
+            replacement: text.to_string(),
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    fn solution(replacements: Vec<Replacement>) -> Solution {
+        Solution {
+            message: "test".to_string(),
+            replacements,
+        }
+    }
+
+    #[test]
+    fn zero_width_insertion_then_adjacent_edit_both_apply() {
+        let mut fix = CodeFix::new("abc");
+        fix.apply(&solution(vec![replacement(0..0, "X")])).unwrap();
+        fix.apply(&solution(vec![replacement(0..1, "Y")])).unwrap();
+        assert_eq!(fix.finish().unwrap(), "XYbc");
+    }
+
+    #[test]
+    fn non_contiguous_replacements_in_one_solution_both_apply() {
+        // The canonical multi-span `Solution`: inserting one token here and
+        // another, unrelated token elsewhere, applied together in a single
+        // `apply()` call.
+        let mut fix = CodeFix::new("abcdef");
+        fix.apply(&solution(vec![
+            replacement(1..1, "X"),
+            replacement(4..4, "Y"),
+        ])).unwrap();
+        assert_eq!(fix.finish().unwrap(), "aXbcdYef");
+    }
+
+    #[test]
+    fn overlapping_replacements_in_one_solution_are_rejected_and_leave_codefix_untouched() {
+        let mut fix = CodeFix::new("abcdef");
+        let result = fix.apply(&solution(vec![
+            replacement(0..3, "XXX"),
+            replacement(2..5, "YYY"),
+        ]));
+        assert!(result.is_err());
+        assert_eq!(fix.finish().unwrap(), "abcdef");
+    }
+}